@@ -7,26 +7,59 @@ use anyhow::Context;
 
 use crate::{load_keyring, or_default_keyring, ProgError};
 
-pub(crate) fn init_keyring(keyring_path: Option<PathBuf>) -> Result<(), ProgError> {
+/// The minimum PBKDF2 iteration count accepted for `--kdf-iterations`.
+pub(crate) const MIN_KDF_ITERATIONS: u32 = 100_000;
+/// The minimum salt size, in bytes, accepted for `--kdf-salt-size`.
+pub(crate) const MIN_KDF_SALT_SIZE: usize = 32;
+
+pub(crate) fn init_keyring(
+    keyring_path: Option<PathBuf>,
+    kdf_iterations: u32,
+    kdf_salt_size: usize,
+    age_work_factor: u8,
+    recipients: Vec<String>,
+) -> Result<(), ProgError> {
     let keyring_path = or_default_keyring(keyring_path)?;
     eprintln!("Creating a new keyring at {}", keyring_path.display());
-    let password = keyring::Secret::from(
-        rpassword::prompt_password("    New password: ")
-            .context("failed to read password from TTY")?,
-    );
-    let confirm_password = keyring::Secret::from(
-        rpassword::prompt_password("Confirm password: ")
-            .context("failed to read password from TTY")?,
-    );
-    if password != confirm_password {
-        return Err(ProgError::InitPasswordsDidntMatch);
-    }
-    keyring::Keyring::create(keyring_path.clone(), password)?;
+
+    let credential = if recipients.is_empty() {
+        if kdf_iterations < MIN_KDF_ITERATIONS {
+            return Err(ProgError::KdfIterationsTooLow(kdf_iterations));
+        }
+        if kdf_salt_size < MIN_KDF_SALT_SIZE {
+            return Err(ProgError::KdfSaltTooShort(kdf_salt_size));
+        }
+        let password = keyring::Secret::from(
+            rpassword::prompt_password("    New password: ")
+                .context("failed to read password from TTY")?,
+        );
+        let confirm_password = keyring::Secret::from(
+            rpassword::prompt_password("Confirm password: ")
+                .context("failed to read password from TTY")?,
+        );
+        if password != confirm_password {
+            return Err(ProgError::InitPasswordsDidntMatch);
+        }
+        keyring::KeyringCredential::Passphrase {
+            password,
+            kdf_params: keyring::KdfParams::generate(kdf_iterations, kdf_salt_size, age_work_factor),
+        }
+    } else {
+        // No identities to supply yet; recipients-mode keyrings are decrypted with a separate
+        // identity file at `get`/`edit`/etc. time, not at creation.
+        keyring::KeyringCredential::Recipients {
+            recipients,
+            identities: Vec::new(),
+        }
+    };
+
+    keyring::Keyring::create(keyring_path.clone(), credential, keyring::KeyringOptions::default())?;
     eprintln!("New keyring created at {}", keyring_path.display());
     Ok(())
 }
 
-pub(crate) fn remove_keyring_item(keyring_path: Option<PathBuf>) -> Result<(), ProgError> {
+/// Delete the item the user selects, returning its name for the caller's `PostSave` hook call.
+pub(crate) fn remove_keyring_item(keyring_path: Option<PathBuf>) -> Result<String, ProgError> {
     let mut keyring = load_keyring(keyring_path)?;
     let selected_item = crate::select::select_item(&keyring)?.to_owned();
     eprintln!(
@@ -39,16 +72,19 @@ pub(crate) fn remove_keyring_item(keyring_path: Option<PathBuf>) -> Result<(), P
         .interact()
         .context("failed to prompt you, somehow")?;
     if confirm_delete {
-        let was_deleted = keyring.delete_item(selected_item);
+        let was_deleted = keyring.delete_item(selected_item.clone());
         assert!(was_deleted);
         keyring.save()?;
-        Ok(())
+        Ok(selected_item)
     } else {
         Err(ProgError::DeleteAborted)
     }
 }
 
-pub(crate) fn list_keyring(keyring_path: Option<PathBuf>) -> Result<(), ProgError> {
+pub(crate) fn list_keyring(
+    keyring_path: Option<PathBuf>,
+    format: crate::table::OutputFormat,
+) -> Result<(), ProgError> {
     let keyring = load_keyring(keyring_path)?;
 
     struct RowMetadata<'a>(keyring::ItemMetadata<'a>);
@@ -68,6 +104,14 @@ pub(crate) fn list_keyring(keyring_path: Option<PathBuf>) -> Result<(), ProgErro
             }
         }
 
+        fn column_key(column_index: usize) -> &'static str {
+            match column_index {
+                0 => "name",
+                1 => "mimetype",
+                _ => panic!(),
+            }
+        }
+
         fn item(&self, column_index: usize) -> &str {
             match column_index {
                 0 => self.0.name,
@@ -78,12 +122,14 @@ pub(crate) fn list_keyring(keyring_path: Option<PathBuf>) -> Result<(), ProgErro
     }
 
     let rows = keyring.item_metadata().map(RowMetadata).collect::<Vec<_>>();
-    crate::table::display_table(&rows, std::io::stdout()).context("failed to output table")?;
+    crate::table::display_rows(format, &rows, std::io::stdout()).context("failed to output list")?;
 
     Ok(())
 }
 
-pub(crate) fn edit_keyring_item(keyring_path: Option<PathBuf>) -> Result<(), ProgError> {
+/// Edit the item the user selects, returning its name for the caller's `PostSave` hook call.
+/// Renaming the item is not supported and panics.
+pub(crate) fn edit_keyring_item(keyring_path: Option<PathBuf>) -> Result<String, ProgError> {
     let mut keyring = load_keyring(keyring_path)?;
     let selected_item = crate::select::select_item(&keyring)?.to_owned();
 
@@ -151,15 +197,45 @@ pub(crate) fn edit_keyring_item(keyring_path: Option<PathBuf>) -> Result<(), Pro
     )?;
     let keyring_item = keyring::ItemOwned {
         mimetype: item_under_edit.mimetype,
-        data: item_data_encoded,
+        data: item_data_encoded.into(),
     };
-    keyring.set_item_raw(item_under_edit.name, keyring_item)?;
+    let edited_name = item_under_edit.name;
+    keyring.set_item_raw(edited_name.clone(), keyring_item)?;
     keyring.save()?;
+    Ok(edited_name)
+}
+
+/// Protect a keyring with `threshold`-of-`shares` Shamir secret sharing, replacing whatever
+/// credential it currently has, then print the resulting shares (one JSON object per line) to
+/// stdout for distribution to custodians.
+pub(crate) fn split_keyring_passphrase(
+    keyring_path: Option<PathBuf>,
+    threshold: u8,
+    shares: u8,
+) -> Result<(), ProgError> {
+    if threshold == 0 {
+        return Err(ProgError::RecoverThresholdZero);
+    }
+    if threshold > shares {
+        return Err(ProgError::RecoverThresholdExceedsShares { threshold, shares });
+    }
+
+    let mut keyring = load_keyring(keyring_path)?;
+    let shares = keyring.split_passphrase(threshold, shares)?;
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for share in &shares {
+        serde_json::to_writer(&mut stdout, share).context("failed to write share to stdout")?;
+        writeln!(stdout).context("failed to write share to stdout")?;
+    }
     Ok(())
 }
 
-pub(crate) fn get_keyring_item(keyring_path: Option<PathBuf>) -> Result<(), ProgError> {
+pub(crate) fn get_keyring_item(keyring_path: Option<PathBuf>, reveal: bool) -> Result<(), ProgError> {
     let mut keyring = load_keyring(keyring_path)?;
+    if reveal {
+        keyring.unlock_hidden_items(crate::prompt_reveal_passphrase()?);
+    }
     let selected_item = crate::select::select_item(&keyring)?.to_owned();
     let item = keyring.get_item_raw(&selected_item)?.unwrap();
     let item = crate::import_export::encode_raw_item_as_yaml(&selected_item, &item);
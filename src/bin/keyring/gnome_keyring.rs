@@ -0,0 +1,147 @@
+//! Importer for the GNOME Keyring on-disk file format, so that users migrating from GNOME's
+//! `secret-service`/`gnome-keyring` don't have to manually transcribe secrets.
+
+use std::io::Read;
+
+use aes::cipher::{BlockDecryptMut, KeyIvInit};
+use anyhow::Context;
+use md5::{Digest, Md5};
+use pbkdf2::pbkdf2_hmac;
+use sha2::Sha256;
+
+/// The minimum PBKDF2 iteration count we'll accept; below this, the file is too weakly protected
+/// to trust, and we refuse to import it rather than silently decrypt with a trivially-brute-forced
+/// key.
+const MIN_ITERATIONS: u32 = 100_000;
+/// The minimum salt size we'll accept, in bytes.
+const MIN_SALT_SIZE: usize = 32;
+
+const MAGIC: &[u8; 16] = b"GnomeKeyring\n\r\0\n";
+
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+
+/// A single credential recovered from a GNOME Keyring file.
+pub(crate) struct GnomeKeyringEntry {
+    pub label: String,
+    pub secret: keyring::Secret,
+}
+
+/// Parse and decrypt a GNOME Keyring file, given the file's own password.
+pub(crate) fn read_gnome_keyring_file(
+    mut data: &[u8],
+    password: &keyring::Secret,
+) -> anyhow::Result<Vec<GnomeKeyringEntry>> {
+    let mut magic = [0u8; 16];
+    read_exact(&mut data, &mut magic).context("failed to read GNOME Keyring magic header")?;
+    if &magic != MAGIC {
+        anyhow::bail!("not a GNOME Keyring file (magic header did not match)");
+    }
+
+    let mut version = [0u8; 2];
+    read_exact(&mut data, &mut version).context("failed to read GNOME Keyring version")?;
+    if version != [1, 0] {
+        anyhow::bail!(
+            "unsupported GNOME Keyring format version {}.{}",
+            version[0],
+            version[1]
+        );
+    }
+
+    let iterations = read_u32_be(&mut data).context("failed to read PBKDF2 iteration count")?;
+    if iterations < MIN_ITERATIONS {
+        anyhow::bail!(
+            "GNOME Keyring file uses only {} PBKDF2 iterations; refusing to import anything \
+             weaker than {}",
+            iterations,
+            MIN_ITERATIONS
+        );
+    }
+
+    let salt_len = read_u32_be(&mut data).context("failed to read salt length")? as usize;
+    if salt_len < MIN_SALT_SIZE {
+        anyhow::bail!(
+            "GNOME Keyring file uses a {}-byte salt; refusing to import anything shorter than \
+             {} bytes",
+            salt_len,
+            MIN_SALT_SIZE
+        );
+    }
+    let mut salt = vec![0u8; salt_len];
+    read_exact(&mut data, &mut salt).context("failed to read salt")?;
+
+    let mut key_iv = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(password.as_str().as_bytes(), &salt, iterations, &mut key_iv);
+    let (key, iv) = key_iv.split_at(16);
+
+    let mut encrypted_blob = Vec::new();
+    data.read_to_end(&mut encrypted_blob)
+        .context("failed to read encrypted item blob")?;
+    if encrypted_blob.len() % 16 != 0 || encrypted_blob.is_empty() {
+        anyhow::bail!("encrypted item blob has an invalid length");
+    }
+
+    let mut decrypted = encrypted_blob;
+    let decrypted = Aes128CbcDec::new(key.into(), iv.into())
+        .decrypt_padded_mut::<aes::cipher::block_padding::Pkcs7>(&mut decrypted)
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "failed to decrypt GNOME Keyring item blob; the password is likely incorrect"
+            )
+        })?
+        .to_vec();
+
+    // The decrypted blob is prefixed with a 16-byte MD5 hash of the remaining bytes, used as an
+    // integrity check (GNOME keyring re-derives and compares this before trusting the contents).
+    if decrypted.len() < 16 {
+        anyhow::bail!("decrypted GNOME Keyring item blob was too short to contain a hash check");
+    }
+    let (hash_check, item_data) = decrypted.split_at(16);
+    let computed_hash = Md5::digest(item_data);
+    if hash_check != computed_hash.as_slice() {
+        anyhow::bail!(
+            "GNOME Keyring item blob failed its integrity check; the password is likely incorrect"
+        );
+    }
+
+    parse_items(item_data)
+}
+
+/// Parse the decrypted, integrity-checked item region into individual entries.
+///
+/// Each entry is a length-prefixed label followed by a length-prefixed secret.
+fn parse_items(mut data: &[u8]) -> anyhow::Result<Vec<GnomeKeyringEntry>> {
+    let mut entries = Vec::new();
+    while !data.is_empty() {
+        let label_len = read_u32_be(&mut data).context("failed to read item label length")? as usize;
+        let mut label = vec![0u8; label_len];
+        read_exact(&mut data, &mut label).context("failed to read item label")?;
+
+        let secret_len = read_u32_be(&mut data).context("failed to read item secret length")? as usize;
+        let mut secret = vec![0u8; secret_len];
+        read_exact(&mut data, &mut secret).context("failed to read item secret")?;
+
+        entries.push(GnomeKeyringEntry {
+            label: String::from_utf8(label).context("item label was not valid UTF-8")?,
+            secret: keyring::Secret::from(
+                String::from_utf8(secret).context("item secret was not valid UTF-8")?,
+            ),
+        });
+    }
+    Ok(entries)
+}
+
+fn read_exact(data: &mut &[u8], buf: &mut [u8]) -> anyhow::Result<()> {
+    if data.len() < buf.len() {
+        anyhow::bail!("unexpected end of GNOME Keyring file");
+    }
+    let (head, tail) = data.split_at(buf.len());
+    buf.copy_from_slice(head);
+    *data = tail;
+    Ok(())
+}
+
+fn read_u32_be(data: &mut &[u8]) -> anyhow::Result<u32> {
+    let mut buf = [0u8; 4];
+    read_exact(data, &mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
@@ -0,0 +1,131 @@
+//! User-scriptable hooks that fire around keyring operations.
+//!
+//! Hook scripts live in `~/.keyring/hooks/<pre-load|post-save>/<name>` and are invoked with the name of the event and
+//! the name of the item (if any) being operated on. This lets users do things like auto-commit
+//! the keyring file to git after it is saved, or run custom audit logging, without forking the
+//! tool.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Context;
+
+/// The operation a hook is firing for.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum HookEvent {
+    NewEntry,
+    ShowEntry,
+    EditEntry,
+    RemoveEntry,
+    ListEntries,
+    Import,
+}
+
+impl HookEvent {
+    fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::NewEntry => "new-entry",
+            HookEvent::ShowEntry => "show-entry",
+            HookEvent::EditEntry => "edit-entry",
+            HookEvent::RemoveEntry => "remove-entry",
+            HookEvent::ListEntries => "list-entries",
+            HookEvent::Import => "import",
+        }
+    }
+}
+
+/// The point in the keyring's lifecycle at which a hook fires.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum HookPoint {
+    /// Run before `load_keyring`.
+    PreLoad,
+    /// Run after `keyring.save()`.
+    PostSave,
+}
+
+impl HookPoint {
+    fn dir_name(self) -> &'static str {
+        match self {
+            HookPoint::PreLoad => "pre-load",
+            HookPoint::PostSave => "post-save",
+        }
+    }
+}
+
+/// Run every hook script registered for `point`, in order of file name, passing it `event`.
+///
+/// Scripts are looked up in `~/.keyring/hooks/<pre-load|post-save>/`. If that directory doesn't
+/// exist, this is a no-op (most users won't have any hooks configured). A non-zero exit from a
+/// `PreLoad` hook aborts the operation; a non-zero exit from a `PostSave` hook is only logged,
+/// since the save has already happened.
+pub(crate) fn run_hooks(
+    point: HookPoint,
+    event: HookEvent,
+    item_name: Option<&str>,
+) -> Result<(), crate::ProgError> {
+    let hook_dir = match hooks_dir() {
+        Some(d) => d.join(point.dir_name()),
+        None => return Ok(()),
+    };
+
+    let entries = match std::fs::read_dir(&hook_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(anyhow::Error::new(err)
+                .context(format!("failed to read hook directory {}", hook_dir.display()))
+                .into())
+        }
+    };
+
+    let mut scripts = entries
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("failed to read hook directory {}", hook_dir.display()))?;
+    scripts.sort_by_key(|e| e.file_name());
+
+    for entry in scripts {
+        let path = entry.path();
+        let mut cmd = Command::new(&path);
+        cmd.arg(event.as_str());
+        if let Some(name) = item_name {
+            cmd.arg(name);
+        }
+        cmd.env("KEYRING_HOOK_EVENT", event.as_str());
+        cmd.env("KEYRING_HOOK_POINT", point.dir_name());
+        if let Some(name) = item_name {
+            cmd.env("KEYRING_HOOK_ITEM", name);
+        }
+
+        let status = cmd
+            .status()
+            .with_context(|| format!("failed to run hook script {}", path.display()))?;
+
+        match (point, status.success()) {
+            (_, true) => (),
+            (HookPoint::PreLoad, false) => {
+                return Err(crate::ProgError::HookAborted(
+                    path.display().to_string(),
+                    status,
+                ))
+            }
+            (HookPoint::PostSave, false) => {
+                eprintln!(
+                    "Warning: post-save hook {} exited with {}",
+                    path.display(),
+                    status
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn hooks_dir() -> Option<PathBuf> {
+    let home = env::var_os("HOME")?;
+    let mut p = PathBuf::from(home);
+    p.push(".keyring");
+    p.push("hooks");
+    Some(p)
+}
@@ -1,5 +1,6 @@
-use std::io;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use base64::Engine;
@@ -8,13 +9,33 @@ use serde::{Deserialize, Serialize};
 use keyring::{KeyringItem, PasswordItem};
 use crate::{load_keyring, ProgError};
 
-pub(crate) fn import(keyring_path: Option<PathBuf>) -> Result<(), ProgError> {
+pub(crate) fn import(keyring_path: Option<PathBuf>, identity: Option<PathBuf>) -> Result<(), ProgError> {
     let mut keyring = load_keyring(keyring_path)?;
+
+    let mut raw_input = Vec::new();
+    io::stdin()
+        .lock()
+        .read_to_end(&mut raw_input)
+        .context("failed to read import data from stdin")?;
+
+    let yaml_input = if crate::openpgp::looks_like_armored_message(&raw_input) {
+        let identity_path = identity
+            .ok_or_else(|| anyhow::anyhow!("this import is OpenPGP-encrypted; pass --identity <key.asc>"))?;
+        let identity_cert = crate::openpgp::read_cert(&identity_path)?;
+        let passphrase = keyring::Secret::from(
+            rpassword::prompt_password("Identity key passphrase: ")
+                .context("failed to read passphrase from TTY")?,
+        );
+        crate::openpgp::decrypt_with_identity(&raw_input, &identity_cert, &passphrase)?
+    } else {
+        raw_input
+    };
+
     let items_to_import = {
         let mut items = Vec::new();
-        for document in serde_yaml::Deserializer::from_reader(io::stdin().lock()) {
+        for document in serde_yaml::Deserializer::from_reader(yaml_input.as_slice()) {
             let item = YamlItem::deserialize(document)
-                .context("failed to read YAML item from stdin")?;
+                .context("failed to read YAML item from the import data")?;
             items.push(item);
         }
         items
@@ -34,7 +55,7 @@ pub(crate) fn import(keyring_path: Option<PathBuf>) -> Result<(), ProgError> {
         validate_item(&item.name, &item.mimetype, &data)?;
         let keyring_item = keyring::ItemOwned {
             mimetype: item.mimetype,
-            data,
+            data: data.into(),
         };
         keyring.set_item_raw(item.name, keyring_item)?;
     }
@@ -44,6 +65,102 @@ pub(crate) fn import(keyring_path: Option<PathBuf>) -> Result<(), ProgError> {
     Ok(())
 }
 
+/// Import every entry out of a GNOME Keyring file at `gnome_keyring_path` into `keyring_path`,
+/// mapping each entry's label and secret into a `keyring::PasswordItem`.
+pub(crate) fn import_gnome_keyring(
+    keyring_path: Option<PathBuf>,
+    gnome_keyring_path: PathBuf,
+) -> Result<(), ProgError> {
+    let mut keyring = load_keyring(keyring_path)?;
+
+    let data = std::fs::read(&gnome_keyring_path)
+        .with_context(|| format!("failed to read {}", gnome_keyring_path.display()))?;
+    let password = keyring::Secret::from(
+        rpassword::prompt_password("GNOME Keyring password: ")
+            .context("failed to read password from TTY")?,
+    );
+    let entries = crate::gnome_keyring::read_gnome_keyring_file(&data, &password)?;
+
+    // Ensure there aren't any conflicts. If there are, we just abort the import.
+    for entry in &entries {
+        if keyring.has_item(&entry.label) {
+            return Err(ProgError::ImportDulicateItem(entry.label.clone()));
+        }
+    }
+
+    let number_of_items = entries.len();
+    for entry in entries {
+        let item = PasswordItem {
+            username: None,
+            email: None,
+            password: entry.secret,
+            security_questions: None,
+            additional: Default::default(),
+        };
+        keyring.set_item(entry.label, &item)?;
+    }
+
+    keyring.save()?;
+    eprintln!("Imported {} items from the GNOME Keyring file.", number_of_items);
+    Ok(())
+}
+
+/// Export every item on the keyring as a single OpenPGP-armored document, encrypted to
+/// `recipients`, and write it to `output` (or stdout, if `output` is `-`).
+pub(crate) fn export(
+    keyring_path: Option<PathBuf>,
+    output: PathBuf,
+    recipients: Vec<PathBuf>,
+    reveal: bool,
+) -> Result<(), ProgError> {
+    let mut keyring = load_keyring(keyring_path)?;
+    if reveal {
+        keyring.unlock_hidden_items(crate::prompt_reveal_passphrase()?);
+    }
+
+    let item_names = keyring
+        .item_metadata()
+        .map(|m| m.name.to_owned())
+        .collect::<Vec<_>>();
+
+    let mut yaml_document = String::new();
+    for name in &item_names {
+        let raw_item = keyring
+            .get_item_raw(name)?
+            .expect("item names were just read from the keyring's own metadata");
+        let yaml_item = encode_raw_item_as_yaml(name, &raw_item);
+        yaml_document.push_str(
+            &serde_yaml::to_string(&yaml_item).context("failed to serialize item as YAML")?,
+        );
+        yaml_document.push_str("---\n");
+    }
+
+    let recipient_certs = recipients
+        .iter()
+        .map(|p| crate::openpgp::read_cert(p))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let encrypted =
+        crate::openpgp::encrypt_to_recipients(yaml_document.as_bytes(), &recipient_certs)?;
+
+    create_or_stdout(&output)?
+        .write_all(&encrypted)
+        .with_context(|| format!("failed to write exported keyring to {}", output.display()))?;
+
+    eprintln!("Exported {} items.", item_names.len());
+    Ok(())
+}
+
+/// Open `path` for writing, unless it is `-`, in which case write to stdout.
+fn create_or_stdout(path: &Path) -> anyhow::Result<Box<dyn Write>> {
+    if path == Path::new("-") {
+        Ok(Box::new(io::stdout()))
+    } else {
+        Ok(Box::new(File::create(path).with_context(|| {
+            format!("failed to create {}", path.display())
+        })?))
+    }
+}
+
 fn validate_item(name: &str, mimetype: &str, data: &[u8]) -> Result<(), ProgError> {
     match mimetype {
         m if m == PasswordItem::mimetype() => {
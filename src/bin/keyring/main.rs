@@ -1,5 +1,6 @@
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
 
 use anyhow::Context;
@@ -7,17 +8,39 @@ use clap::Parser;
 
 mod base_operations;
 mod editor;
+mod gnome_keyring;
+mod hooks;
 mod import_export;
+mod openpgp;
+mod otp;
 mod pw;
 mod select;
 mod table;
 
+use hooks::{HookEvent, HookPoint};
+
 #[derive(Parser)]
 enum Args {
     /// Create a new keyring file.
     Init {
         #[arg(long)]
         keyring: Option<PathBuf>,
+        /// PBKDF2 iterations used to stretch the master passphrase before encryption.
+        #[arg(long, default_value_t = 210_000)]
+        kdf_iterations: u32,
+        /// Size, in bytes, of the random salt used when stretching the master passphrase.
+        #[arg(long, default_value_t = 32)]
+        kdf_salt_size: usize,
+        /// Recorded alongside the keyring for reference; age picks its own scrypt work factor at
+        /// encrypt time and isn't configurable, so this does not actually change how hard the
+        /// keyring is to brute-force.
+        #[arg(long, default_value_t = keyring::KdfParams::DEFAULT_AGE_WORK_FACTOR)]
+        age_work_factor: u8,
+        /// Encrypt the keyring to an asymmetric age recipient (an `age1...` public key, or an
+        /// `ssh-ed25519`/`ssh-rsa` public key) instead of a passphrase. May be given more than
+        /// once; if given at all, the `--kdf-*`/`--age-work-factor` flags are ignored.
+        #[arg(long = "recipient")]
+        recipients: Vec<String>,
     },
     /// Remove an item from a keyring.
     Remove {
@@ -28,6 +51,9 @@ enum Args {
     List {
         #[arg(long)]
         keyring: Option<PathBuf>,
+        /// How to render the list of items.
+        #[arg(long, value_enum, default_value = "table")]
+        format: table::OutputFormat,
     },
     /// Edit the raw data for an item.
     Edit {
@@ -38,15 +64,64 @@ enum Args {
     Get {
         #[arg(long)]
         keyring: Option<PathBuf>,
+        /// Prompt for the reveal passphrase, to allow reading a hidden item.
+        #[arg(long)]
+        reveal: bool,
     },
     /// Import items into the keyring.
     Import {
         #[arg(long)]
         keyring: Option<PathBuf>,
+        /// Certificate (with secret key) to decrypt an OpenPGP-encrypted import with.
+        #[arg(long)]
+        identity: Option<PathBuf>,
+        /// Import a GNOME Keyring file, instead of reading items as YAML from stdin.
+        #[arg(long)]
+        gnome_keyring: Option<PathBuf>,
+    },
+    /// Export items from the keyring as an OpenPGP-encrypted, ASCII-armored document.
+    Export {
+        #[arg(long)]
+        keyring: Option<PathBuf>,
+        /// Where to write the exported document; `-` writes to stdout.
+        #[arg(long, default_value = "-")]
+        output: PathBuf,
+        /// Certificate to encrypt the export to. May be given more than once.
+        #[arg(long = "recipient", required = true)]
+        recipients: Vec<PathBuf>,
+        /// Prompt for the reveal passphrase, so hidden items can be included in the export
+        /// instead of aborting the whole export the moment one is encountered.
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// Protect a keyring with k-of-n Shamir secret sharing instead of its current credential,
+    /// printing the resulting shares (one JSON object per line) to stdout for distribution to
+    /// custodians. Any `--threshold` of the printed shares can later reconstruct the keyring via
+    /// `Keyring::load_from_shares`.
+    Recover {
+        #[arg(long)]
+        keyring: Option<PathBuf>,
+        /// Number of shares required to reconstruct the keyring.
+        #[arg(long)]
+        threshold: u8,
+        /// Total number of shares to generate.
+        #[arg(long)]
+        shares: u8,
     },
     /// Commands for dealing with password items.
     #[command(subcommand)]
     Password(PasswordCommand),
+    /// Commands for dealing with one-time-password (TOTP) items.
+    #[command(subcommand)]
+    Otp(OtpCommand),
+    /// Not a user-facing command. `pw copy` re-execs itself into this, detached from the parent
+    /// process, so the clipboard gets cleared after `clear_after` seconds without the foreground
+    /// `pw copy` invocation blocking for that long.
+    #[command(hide = true, name = "__internal-clear-clipboard")]
+    InternalClearClipboard {
+        #[arg(long)]
+        clear_after: u64,
+    },
 }
 
 #[derive(clap::Subcommand)]
@@ -55,6 +130,16 @@ enum PasswordCommand {
     New {
         #[arg(long)]
         keyring: Option<PathBuf>,
+        /// Whether to generate a random-character password, or a diceware-style passphrase.
+        #[arg(long, value_enum, default_value = "random")]
+        style: pw::PasswordStyle,
+        /// Number of words to use, when `--style passphrase`.
+        #[arg(long, default_value_t = 6)]
+        words: usize,
+        /// Mark the new item hidden, encrypting it with a separate reveal passphrase you'll be
+        /// prompted for.
+        #[arg(long)]
+        hidden: bool,
     },
     /// Generate a new password, and edit the result in your editor to collect additional details
     /// such as the item name, the username, or the security questions. The saved result is then
@@ -62,11 +147,55 @@ enum PasswordCommand {
     EditNew {
         #[arg(long)]
         keyring: Option<PathBuf>,
+        /// Whether to generate a random-character password, or a diceware-style passphrase.
+        #[arg(long, value_enum, default_value = "random")]
+        style: pw::PasswordStyle,
+        /// Number of words to use, when `--style passphrase`.
+        #[arg(long, default_value_t = 6)]
+        words: usize,
+        /// Mark the new item hidden, encrypting it with a separate reveal passphrase you'll be
+        /// prompted for.
+        #[arg(long)]
+        hidden: bool,
     },
     /// Copy a password item's password to the clipboard.
     Copy {
         #[arg(long)]
         keyring: Option<PathBuf>,
+        /// How many seconds to leave the password on the clipboard before clearing it.
+        #[arg(long, default_value_t = 45)]
+        clear_after: u64,
+    },
+}
+
+#[derive(clap::Subcommand)]
+enum OtpCommand {
+    /// Provision a new TOTP item from a base32 shared secret, as found in most `otpauth://`
+    /// provisioning URIs.
+    New {
+        #[arg(long)]
+        keyring: Option<PathBuf>,
+        /// Which HMAC hash the code is computed with.
+        #[arg(long, value_enum, default_value = "sha1")]
+        algorithm: otp::OtpAlgorithm,
+        /// Number of digits in each generated code.
+        #[arg(long, default_value_t = keyring::OtpItem::DEFAULT_DIGITS)]
+        digits: u32,
+        /// Validity period of each code, in seconds.
+        #[arg(long, default_value_t = keyring::OtpItem::DEFAULT_PERIOD)]
+        period: u64,
+        /// Mark the new item hidden, encrypting it with a separate reveal passphrase you'll be
+        /// prompted for.
+        #[arg(long)]
+        hidden: bool,
+    },
+    /// Print the currently-valid code for an OTP item.
+    Show {
+        #[arg(long)]
+        keyring: Option<PathBuf>,
+        /// Prompt for the reveal passphrase, so hidden items can be shown instead of erroring.
+        #[arg(long)]
+        reveal: bool,
     },
 }
 
@@ -74,17 +203,110 @@ fn run() -> Result<(), ProgError> {
     let args = Args::parse();
 
     match args {
-        Args::Init { keyring } => base_operations::init_keyring(keyring)?,
-        Args::Remove { keyring } => base_operations::remove_keyring_item(keyring)?,
-        Args::List { keyring } => base_operations::list_keyring(keyring)?,
-        Args::Edit { keyring } => base_operations::edit_keyring_item(keyring)?,
-        Args::Get { keyring } => base_operations::get_keyring_item(keyring)?,
-        Args::Import { keyring } => import_export::import(keyring)?,
-        Args::Password(PasswordCommand::New { keyring }) => unimplemented!(),
-        Args::Password(PasswordCommand::EditNew { keyring }) => {
-            pw::edit_new_password(keyring)?;
+        Args::Init {
+            keyring,
+            kdf_iterations,
+            kdf_salt_size,
+            age_work_factor,
+            recipients,
+        } => base_operations::init_keyring(
+            keyring,
+            kdf_iterations,
+            kdf_salt_size,
+            age_work_factor,
+            recipients,
+        )?,
+        Args::Remove { keyring } => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::RemoveEntry, None)?;
+            let removed_item = base_operations::remove_keyring_item(keyring)?;
+            hooks::run_hooks(HookPoint::PostSave, HookEvent::RemoveEntry, Some(&removed_item))?;
+        }
+        Args::List { keyring, format } => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::ListEntries, None)?;
+            base_operations::list_keyring(keyring, format)?;
+        }
+        Args::Edit { keyring } => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::EditEntry, None)?;
+            let edited_item = base_operations::edit_keyring_item(keyring)?;
+            hooks::run_hooks(HookPoint::PostSave, HookEvent::EditEntry, Some(&edited_item))?;
+        }
+        Args::Get { keyring, reveal } => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::ShowEntry, None)?;
+            base_operations::get_keyring_item(keyring, reveal)?;
+        }
+        Args::Import {
+            keyring,
+            identity,
+            gnome_keyring,
+        } => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::Import, None)?;
+            match gnome_keyring {
+                Some(gnome_keyring_path) => {
+                    import_export::import_gnome_keyring(keyring, gnome_keyring_path)?
+                }
+                None => import_export::import(keyring, identity)?,
+            }
+            hooks::run_hooks(HookPoint::PostSave, HookEvent::Import, None)?;
+        }
+        Args::Export {
+            keyring,
+            output,
+            recipients,
+            reveal,
+        } => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::ListEntries, None)?;
+            import_export::export(keyring, output, recipients, reveal)?;
+        }
+        Args::Recover {
+            keyring,
+            threshold,
+            shares,
+        } => base_operations::split_keyring_passphrase(keyring, threshold, shares)?,
+        Args::Password(PasswordCommand::New {
+            keyring,
+            style,
+            words,
+            hidden,
+        }) => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::NewEntry, None)?;
+            let new_item = pw::new_password(keyring, style, words, hidden)?;
+            hooks::run_hooks(HookPoint::PostSave, HookEvent::NewEntry, Some(&new_item))?;
+        }
+        Args::Password(PasswordCommand::EditNew {
+            keyring,
+            style,
+            words,
+            hidden,
+        }) => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::NewEntry, None)?;
+            let new_item = pw::edit_new_password(keyring, style, words, hidden)?;
+            hooks::run_hooks(HookPoint::PostSave, HookEvent::NewEntry, Some(&new_item))?;
+        }
+        Args::Password(PasswordCommand::Copy {
+            keyring,
+            clear_after,
+        }) => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::ShowEntry, None)?;
+            pw::copy_password(keyring, clear_after)?;
+        }
+        Args::Otp(OtpCommand::New {
+            keyring,
+            algorithm,
+            digits,
+            period,
+            hidden,
+        }) => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::NewEntry, None)?;
+            let new_item = otp::new_otp(keyring, algorithm, digits, period, hidden)?;
+            hooks::run_hooks(HookPoint::PostSave, HookEvent::NewEntry, Some(&new_item))?;
+        }
+        Args::Otp(OtpCommand::Show { keyring, reveal }) => {
+            hooks::run_hooks(HookPoint::PreLoad, HookEvent::ShowEntry, None)?;
+            otp::show_otp_code(keyring, reveal)?;
+        }
+        Args::InternalClearClipboard { clear_after } => {
+            pw::run_clipboard_clearer(clear_after)?;
         }
-        Args::Password(PasswordCommand::Copy { keyring }) => pw::copy_password(keyring)?,
     }
 
     Ok(())
@@ -121,12 +343,51 @@ fn default_keyring() -> anyhow::Result<PathBuf> {
     Ok(default_path)
 }
 
+/// Prompt for the "reveal" passphrase that protects hidden items, for commands run with
+/// `--hidden`/`--reveal`.
+fn prompt_reveal_passphrase() -> Result<keyring::Secret, ProgError> {
+    Ok(keyring::Secret::from(
+        rpassword::prompt_password("Reveal passphrase: ")
+            .context("failed to read reveal passphrase from TTY")?,
+    ))
+}
+
 fn load_keyring(keyring_path: Option<PathBuf>) -> Result<keyring::Keyring, ProgError> {
     let keyring_path = or_default_keyring(keyring_path)?;
-    let password = keyring::Secret::from(
-        rpassword::prompt_password("Password: ").context("failed to read password from TTY")?,
-    );
-    Ok(keyring::Keyring::load(keyring_path, password)?)
+    let credential = prompt_credential(&keyring_path)?;
+    Ok(keyring::Keyring::load(keyring_path, credential)?)
+}
+
+/// Prompt for whatever credential the keyring at `keyring_path` turns out to need: a passphrase,
+/// or the path to an age/SSH identity file.
+fn prompt_credential(keyring_path: &Path) -> Result<keyring::KeyringCredential, ProgError> {
+    match keyring::Keyring::peek_credential_kind(keyring_path)? {
+        keyring::CredentialKind::Passphrase => {
+            let password = keyring::Secret::from(
+                rpassword::prompt_password("Password: ")
+                    .context("failed to read password from TTY")?,
+            );
+            Ok(keyring::KeyringCredential::Passphrase {
+                password,
+                // Ignored: `Keyring::load` overwrites this with the parameters recorded in the
+                // keyring's own header.
+                kdf_params: keyring::KdfParams::generate(1, 0, 0),
+            })
+        }
+        keyring::CredentialKind::Recipients => {
+            let identity_path: PathBuf = dialoguer::Input::new()
+                .with_prompt("Identity file")
+                .interact_text()
+                .context("failed to prompt you, somehow")?;
+            let identity = fs::read_to_string(&identity_path).with_context(|| {
+                format!("failed to read identity file {}", identity_path.display())
+            })?;
+            Ok(keyring::KeyringCredential::Recipients {
+                recipients: Vec::new(),
+                identities: vec![keyring::Secret::from(identity)],
+            })
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -147,6 +408,29 @@ enum ProgError {
     ImportValidationFailed(String, #[source] anyhow::Error),
     #[error("The item named {0:?} was not a password item.")]
     NotAPasswordItem(String),
+    #[error("The item named {0:?} was not an OTP item.")]
+    NotAnOtpItem(String),
+    #[error("The keyring already contains an item named {0:?}.")]
+    ItemAlreadyExists(String),
+    #[error("pre-load hook {0} aborted the operation (exited with {1})")]
+    HookAborted(String, std::process::ExitStatus),
+    #[error(
+        "--kdf-iterations must be at least {}, but {0} was given",
+        base_operations::MIN_KDF_ITERATIONS
+    )]
+    KdfIterationsTooLow(u32),
+    #[error(
+        "--kdf-salt-size must be at least {} bytes, but {0} was given",
+        base_operations::MIN_KDF_SALT_SIZE
+    )]
+    KdfSaltTooShort(usize),
+    #[error("--threshold must be at least 1, but 0 was given")]
+    RecoverThresholdZero,
+    #[error(
+        "--threshold ({threshold}) cannot be greater than --shares ({shares}); that many shares \
+         could never reconstruct the keyring"
+    )]
+    RecoverThresholdExceedsShares { threshold: u8, shares: u8 },
     #[error("Keyring error: {0}")]
     Keyring(keyring::KeyringError),
     #[error(transparent)]
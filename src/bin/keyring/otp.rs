@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+use crate::{load_keyring, ProgError};
+use keyring::{KeyringItem, OtpItem};
+
+/// Which HMAC hash `keyring otp new` should provision the item with. Mirrors
+/// [`keyring::OtpAlgorithm`]; kept as a separate type so the lib crate doesn't need to depend on
+/// `clap`.
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub(crate) enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl From<OtpAlgorithm> for keyring::OtpAlgorithm {
+    fn from(algorithm: OtpAlgorithm) -> keyring::OtpAlgorithm {
+        match algorithm {
+            OtpAlgorithm::Sha1 => keyring::OtpAlgorithm::Sha1,
+            OtpAlgorithm::Sha256 => keyring::OtpAlgorithm::Sha256,
+            OtpAlgorithm::Sha512 => keyring::OtpAlgorithm::Sha512,
+        }
+    }
+}
+
+/// Provision a new TOTP item from a base32 shared secret, as found in most `otpauth://`
+/// provisioning URIs. Returns the new item's name, for the caller's `PostSave` hook call.
+pub(crate) fn new_otp(
+    keyring_path: Option<PathBuf>,
+    algorithm: OtpAlgorithm,
+    digits: u32,
+    period: u64,
+    hidden: bool,
+) -> Result<String, ProgError> {
+    let mut keyring = load_keyring(keyring_path)?;
+    if hidden {
+        keyring.unlock_hidden_items(crate::prompt_reveal_passphrase()?);
+    }
+
+    let name: String = dialoguer::Input::new()
+        .with_prompt("Item name")
+        .interact_text()
+        .context("failed to prompt you, somehow")?;
+    if keyring.has_item(&name) {
+        return Err(ProgError::ItemAlreadyExists(name));
+    }
+
+    let base32_secret = rpassword::prompt_password("Base32 shared secret: ")
+        .context("failed to read shared secret from TTY")?;
+    let item = OtpItem::from_base32_secret(&base32_secret, algorithm.into(), digits, period)?;
+
+    if hidden {
+        keyring.set_item_hidden(name.clone(), &item)?;
+    } else {
+        keyring.set_item(name.clone(), &item)?;
+    }
+    keyring.save()?;
+    Ok(name)
+}
+
+/// Print the currently-valid TOTP code for the item the user selects.
+pub(crate) fn show_otp_code(keyring_path: Option<PathBuf>, reveal: bool) -> Result<(), ProgError> {
+    let mut keyring = load_keyring(keyring_path)?;
+    if reveal {
+        keyring.unlock_hidden_items(crate::prompt_reveal_passphrase()?);
+    }
+
+    let selected_item = crate::select::select_item(&keyring)?.to_owned();
+    let raw_item = keyring
+        .get_item_raw(&selected_item)?
+        .expect("the selected item should always exist on the keyring");
+    if raw_item.mimetype == OtpItem::mimetype() {
+        let otp_item = <OtpItem as KeyringItem>::deserialize(&raw_item.data)?;
+        let code = otp_item.current_code()?;
+        println!("{}", code);
+        Ok(())
+    } else {
+        Err(ProgError::NotAnOtpItem(selected_item))
+    }
+}
@@ -1,6 +1,9 @@
+use std::env;
 use std::io::{Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
 
 use anyhow::Context;
 use serde::Deserialize;
@@ -8,26 +11,92 @@ use serde::Deserialize;
 use crate::{load_keyring, ProgError};
 use keyring::{KeyringItem, PasswordItem};
 
-fn create_password(keyring_path: Option<PathBuf>) -> anyhow::Result<()> {
-    unimplemented!()
+/// Which kind of password `keyring pw new`/`edit-new` should generate.
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub(crate) enum PasswordStyle {
+    /// A random string of letters, numbers, and symbols.
+    Random,
+    /// A diceware-style passphrase made of randomly-chosen words.
+    Passphrase,
 }
 
-pub(crate) fn edit_new_password(keyring_path: Option<PathBuf>) -> Result<(), ProgError> {
+/// Generate a new password per `style`/`words`, as used by both `pw new` and `pw edit-new`.
+fn generate_password(style: PasswordStyle, words: usize) -> keyring::Secret {
+    let mut rng = rand::thread_rng();
+    match style {
+        PasswordStyle::Random => {
+            let alphabet = {
+                let mut abc = Vec::new();
+                abc.extend(LETTERS.chars());
+                abc.extend(NUMBERS.chars());
+                abc.extend(SYMBOLS.chars());
+                abc
+            };
+            keyring::password_generation::generate_random_password(&mut rng, &alphabet, 16)
+        }
+        PasswordStyle::Passphrase => {
+            let wordlist = keyring::password_generation::wordlist();
+            let entropy =
+                keyring::password_generation::passphrase_entropy_bits(wordlist.len(), words);
+            eprintln!("Generated a {:.1}-bit passphrase.", entropy);
+            keyring::password_generation::generate_passphrase(&mut rng, &wordlist, words, '-')
+        }
+    }
+}
+
+/// Generate a new password and store it directly under a name you type at a prompt, with no
+/// editor round-trip. See [`edit_new_password`] if you also want to set a username, email, or
+/// security questions. Returns the new item's name, for the caller's `PostSave` hook call.
+pub(crate) fn new_password(
+    keyring_path: Option<PathBuf>,
+    style: PasswordStyle,
+    words: usize,
+    hidden: bool,
+) -> Result<String, ProgError> {
     let mut keyring = crate::load_keyring(keyring_path)?;
+    if hidden {
+        keyring.unlock_hidden_items(crate::prompt_reveal_passphrase()?);
+    }
 
-    let mut rng = rand::thread_rng();
-    let alphabet = {
-        // TODO: make configurable
-        let mut abc = Vec::new();
-        abc.extend(LETTERS.chars());
-        abc.extend(NUMBERS.chars());
-        abc.extend(SYMBOLS.chars());
-        abc
+    let new_password = generate_password(style, words);
+
+    let name: String = dialoguer::Input::new()
+        .with_prompt("Item name")
+        .interact_text()
+        .context("failed to prompt you, somehow")?;
+    if keyring.has_item(&name) {
+        return Err(ProgError::ItemAlreadyExists(name));
+    }
+
+    let item = PasswordItem {
+        username: None,
+        email: None,
+        password: new_password,
+        security_questions: None,
+        additional: Default::default(),
     };
-    let new_password = keyring::password_generation::generate_random_password(
-        &mut rng, &alphabet, // TODO: configurable
-        16,
-    );
+    if hidden {
+        keyring.set_item_hidden(name.clone(), &item)?;
+    } else {
+        keyring.set_item(name.clone(), &item)?;
+    }
+    keyring.save()?;
+    Ok(name)
+}
+
+/// Returns the new item's name, for the caller's `PostSave` hook call.
+pub(crate) fn edit_new_password(
+    keyring_path: Option<PathBuf>,
+    style: PasswordStyle,
+    words: usize,
+    hidden: bool,
+) -> Result<String, ProgError> {
+    let mut keyring = crate::load_keyring(keyring_path)?;
+    if hidden {
+        keyring.unlock_hidden_items(crate::prompt_reveal_passphrase()?);
+    }
+
+    let new_password = generate_password(style, words);
 
     let mut temp_file = tempfile::Builder::new()
         .suffix(".yaml")
@@ -94,12 +163,20 @@ pub(crate) fn edit_new_password(keyring_path: Option<PathBuf>) -> Result<(), Pro
             }
         }
     };
-    keyring.set_item(parsed_item.name, &parsed_item.spec)?;
+    let item_name = parsed_item.name;
+    if hidden {
+        keyring.set_item_hidden(item_name.clone(), &parsed_item.spec)?;
+    } else {
+        keyring.set_item(item_name.clone(), &parsed_item.spec)?;
+    }
     keyring.save()?;
-    Ok(())
+    Ok(item_name)
 }
 
-pub(crate) fn copy_password(keyring_path: Option<PathBuf>) -> Result<(), ProgError> {
+pub(crate) fn copy_password(
+    keyring_path: Option<PathBuf>,
+    clear_after: u64,
+) -> Result<(), ProgError> {
     let mut keyring = load_keyring(keyring_path)?;
     let selected_item = crate::select::select_item(&keyring)?.to_owned();
     let raw_item = keyring
@@ -107,33 +184,79 @@ pub(crate) fn copy_password(keyring_path: Option<PathBuf>) -> Result<(), ProgErr
         .expect("the selected item should always exist on the keyring");
     if raw_item.mimetype == PasswordItem::mimetype() {
         let password_item = <PasswordItem as keyring::KeyringItem>::deserialize(&raw_item.data)?;
-        send_to_clipboard(password_item.password.as_str().as_bytes())?;
-        eprintln!("Copied to the clipboard.");
+        send_to_clipboard(password_item.password.as_str(), clear_after)?;
+        eprintln!(
+            "Copied to the clipboard; it will be cleared in {} seconds.",
+            clear_after
+        );
         Ok(())
     } else {
         Err(ProgError::NotAPasswordItem(selected_item))
     }
 }
 
-fn send_to_clipboard(data: &[u8]) -> anyhow::Result<()> {
-    let mut child = clipboard_cmd()
-        .stdin(Stdio::piped())
-        .spawn()?;
-    child.stdin.as_mut().unwrap().write_all(data)?;
-    child.wait()?;
+/// The environment variables `send_to_clipboard` passes the secret (and prior clipboard contents)
+/// through to the detached `__internal-clear-clipboard` process, rather than argv, so they don't
+/// show up in `ps`.
+const CLEAR_SECRET_VAR: &str = "KEYRING_CLIPBOARD_SECRET";
+const CLEAR_PREVIOUS_VAR: &str = "KEYRING_CLIPBOARD_PREVIOUS";
+
+/// Copy `secret` to the clipboard, then re-exec ourselves into a detached
+/// `__internal-clear-clipboard` process that will restore whatever was on the clipboard before us
+/// (or clear it) once `clear_after` seconds elapse. The detached process outlives us, so unlike an
+/// in-process thread, `copy_password` doesn't need to block waiting for it.
+fn send_to_clipboard(secret: &str, clear_after: u64) -> anyhow::Result<()> {
+    let mut clipboard = arboard::Clipboard::new().context("failed to access the clipboard")?;
+    let previous_contents = clipboard.get_text().ok();
+
+    clipboard
+        .set_text(secret.to_owned())
+        .context("failed to write the password to the clipboard")?;
+
+    let this_exe = env::current_exe().context("failed to find the path to the keyring binary")?;
+    let mut clearer = Command::new(this_exe);
+    clearer
+        .arg("__internal-clear-clipboard")
+        .arg("--clear-after")
+        .arg(clear_after.to_string())
+        .env(CLEAR_SECRET_VAR, secret)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(previous) = previous_contents {
+        clearer.env(CLEAR_PREVIOUS_VAR, previous);
+    }
+    clearer
+        .spawn()
+        .context("failed to spawn the background clipboard-clearing process")?;
+
     Ok(())
 }
 
-#[cfg(target_os = "macos")]
-fn clipboard_cmd() -> Command {
-    Command::new("pbcopy")
-}
+/// The body of the hidden `__internal-clear-clipboard` command: sleep for `clear_after` seconds,
+/// then restore the clipboard's previous contents (or clear it, if there were none) if (and only
+/// if) it still contains the secret `send_to_clipboard` put there, so we don't clobber something
+/// the user copied in the meantime.
+pub(crate) fn run_clipboard_clearer(clear_after: u64) -> Result<(), ProgError> {
+    thread::sleep(Duration::from_secs(clear_after));
 
-#[cfg(not(target_os = "macos"))]
-fn clipboard_cmd() -> Command {
-    let mut cmd = Command::new("xsel");
-    cmd.arg("-b");
-    cmd
+    let secret = env::var(CLEAR_SECRET_VAR)
+        .context("__internal-clear-clipboard run without its secret environment variable")?;
+    let previous_contents = env::var(CLEAR_PREVIOUS_VAR).ok();
+
+    let mut clipboard = arboard::Clipboard::new().context("failed to access the clipboard")?;
+    if clipboard.get_text().as_deref() != Ok(secret.as_str()) {
+        return Ok(());
+    }
+    match previous_contents {
+        Some(previous) => {
+            let _ = clipboard.set_text(previous);
+        }
+        None => {
+            let _ = clipboard.set_text(String::new());
+        }
+    }
+    Ok(())
 }
 
 #[derive(Deserialize)]
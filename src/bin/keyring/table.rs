@@ -3,6 +3,31 @@ use std::io::{self, Write};
 
 use unicode_width::UnicodeWidthStr;
 
+/// How to render a set of rows that implement [`TableDisplay`].
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// The human-aligned Unicode table, as rendered by [`display_table`].
+    Table,
+    /// A JSON array of one object per row, keyed by [`TableDisplay::column_key`].
+    Json,
+    /// RFC-4180 CSV, with a header row of column names.
+    Csv,
+}
+
+/// Render `rows` in the given `format`, dispatching to whichever of [`display_table`],
+/// [`display_json`], or [`display_csv`] matches.
+pub fn display_rows<ItemType: TableDisplay>(
+    format: OutputFormat,
+    rows: &[ItemType],
+    output: impl Write,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Table => display_table(rows, output),
+        OutputFormat::Json => display_json(rows, output),
+        OutputFormat::Csv => display_csv(rows, output),
+    }
+}
+
 pub fn display_table<ItemType: TableDisplay>(
     rows: &[ItemType],
     mut output: impl Write,
@@ -43,12 +68,93 @@ pub fn display_table<ItemType: TableDisplay>(
     Ok(())
 }
 
+/// Serialize `rows` as a JSON array, one object per row, keyed by [`TableDisplay::column_key`].
+pub fn display_json<ItemType: TableDisplay>(
+    rows: &[ItemType],
+    output: impl Write,
+) -> io::Result<()> {
+    let column_count = ItemType::columns();
+    let objects = rows
+        .iter()
+        .map(|row| {
+            let mut object = serde_json::Map::with_capacity(column_count);
+            for column_index in 0..column_count {
+                object.insert(
+                    ItemType::column_key(column_index).to_owned(),
+                    serde_json::Value::String(row.item(column_index).to_owned()),
+                );
+            }
+            serde_json::Value::Object(object)
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_writer_pretty(output, &serde_json::Value::Array(objects))?;
+    Ok(())
+}
+
+/// Serialize `rows` as RFC-4180 CSV, with a header row of column names.
+pub fn display_csv<ItemType: TableDisplay>(
+    rows: &[ItemType],
+    mut output: impl Write,
+) -> io::Result<()> {
+    let column_count = ItemType::columns();
+    write_csv_row(&mut output, column_count, |column_index| {
+        CsvField(ItemType::column_name(column_index))
+    })?;
+    for row in rows {
+        write_csv_row(&mut output, column_count, |column_index| {
+            CsvField(row.item(column_index))
+        })?;
+    }
+    Ok(())
+}
+
+fn write_csv_row<F, D>(
+    mut output: impl Write,
+    column_count: usize,
+    get_column_display: F,
+) -> io::Result<()>
+where
+    D: Display,
+    F: Fn(usize) -> D,
+{
+    let mut first = true;
+    for column_index in 0..column_count {
+        if first {
+            first = false;
+        } else {
+            write!(&mut output, ",")?;
+        }
+        write!(&mut output, "{}", get_column_display(column_index))?;
+    }
+    write!(&mut output, "\r\n")?;
+    Ok(())
+}
+
+struct CsvField<'a>(&'a str);
+
+impl Display for CsvField<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.contains(['"', ',', '\n', '\r']) {
+            write!(f, "\"{}\"", self.0.replace('"', "\"\""))
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
 pub trait TableDisplay {
     type Item: Display;
 
     fn columns() -> usize;
     fn column_name(column_index: usize) -> &'static str;
     fn item(&self, column_index: usize) -> &str;
+
+    /// The key used for this column in [`display_json`]'s output. Defaults to [`Self::column_name`],
+    /// but implementors whose display headers aren't machine-friendly (e.g. multi-word, capitalized
+    /// headers meant for `display_table`) should override this with a stable, script-friendly key.
+    fn column_key(column_index: usize) -> &'static str {
+        Self::column_name(column_index)
+    }
 }
 
 fn output_row<F, D>(
@@ -0,0 +1,48 @@
+//! Credentials a keyring can be protected with: either a shared master passphrase, or a set of
+//! asymmetric age recipients/identities.
+
+use std::str::FromStr;
+
+use crate::{KdfParams, Secret};
+
+/// How a keyring's master key is protected.
+pub enum KeyringCredential {
+    /// A single passphrase, shared by everyone who should be able to open the keyring. Stretched
+    /// via `kdf_params` before being handed to age.
+    Passphrase {
+        password: Secret,
+        kdf_params: KdfParams,
+    },
+    /// One or more asymmetric age recipients (X25519 or SSH), each of whom can open the keyring
+    /// with their own identity. Mirrors a multi-recipient GPG vault: several holders can each
+    /// decrypt the same keyring without sharing a single secret.
+    Recipients {
+        /// Recipients to (re-)encrypt the keyring to, on `create`/`save`.
+        recipients: Vec<String>,
+        /// Identities to decrypt the keyring with, on `load`. Wrapped in [`Secret`] since these
+        /// are raw age/SSH private key material, not public recipient strings.
+        identities: Vec<Secret>,
+    },
+}
+
+/// Parse a recipient string as either an X25519 or an SSH age recipient.
+pub(crate) fn parse_recipient(s: &str) -> anyhow::Result<Box<dyn age::Recipient + Send>> {
+    if let Ok(recipient) = age::x25519::Recipient::from_str(s) {
+        return Ok(Box::new(recipient));
+    }
+    if let Ok(recipient) = age::ssh::Recipient::from_str(s) {
+        return Ok(Box::new(recipient));
+    }
+    anyhow::bail!("{:?} is not a recognized X25519 or SSH age recipient", s)
+}
+
+/// Parse an identity string as either an X25519 or an SSH age identity.
+pub(crate) fn parse_identity(s: &str) -> anyhow::Result<Box<dyn age::Identity>> {
+    if let Ok(identity) = age::x25519::Identity::from_str(s) {
+        return Ok(Box::new(identity));
+    }
+    if let Ok(identity) = age::ssh::Identity::from_buffer(s.as_bytes(), None) {
+        return Ok(Box::new(identity));
+    }
+    anyhow::bail!("not a recognized X25519 or SSH age identity")
+}
@@ -0,0 +1,56 @@
+//! Key-derivation hardening parameters for a keyring's master passphrase.
+
+use base64::Engine;
+use pbkdf2::pbkdf2_hmac;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+/// The PBKDF2-HMAC-SHA256 parameters used to stretch a keyring's master passphrase before it is
+/// handed to the underlying age encryption. Chosen at [`crate::Keyring::create`] time and
+/// persisted in the keyring's header, so [`crate::Keyring::load`] re-derives with the same
+/// parameters the file was written with, rather than assuming defaults.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct KdfParams {
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    /// Recorded for informational/forward-compatibility purposes only: `age::Encryptor` picks its
+    /// own scrypt work factor at encrypt time and doesn't accept one as input, so this value does
+    /// not actually configure (or bound) the work factor a keyring was encrypted with, and isn't
+    /// enforced on decrypt.
+    pub age_work_factor: u8,
+}
+
+impl KdfParams {
+    /// The age passphrase work factor used when none is given explicitly.
+    pub const DEFAULT_AGE_WORK_FACTOR: u8 = 20;
+
+    /// Generate fresh, random KDF parameters with the given work factor and salt size.
+    ///
+    /// Callers are expected to have already enforced any minimums they care about; this
+    /// constructor doesn't second-guess the caller's choice of iteration count or salt size.
+    pub fn generate(iterations: u32, salt_size: usize, age_work_factor: u8) -> KdfParams {
+        let mut salt = vec![0u8; salt_size];
+        rand::thread_rng().fill_bytes(&mut salt);
+        KdfParams {
+            iterations,
+            salt,
+            age_work_factor,
+        }
+    }
+
+    /// Stretch `password` into the passphrase actually passed to age.
+    pub(crate) fn stretch(&self, password: &crate::Secret) -> crate::Secret {
+        let mut out = [0u8; 32];
+        pbkdf2_hmac::<Sha256>(
+            password.as_str().as_bytes(),
+            &self.salt,
+            self.iterations,
+            &mut out,
+        );
+        let stretched = crate::Secret::from(base64::engine::general_purpose::STANDARD.encode(out));
+        out.zeroize();
+        stretched
+    }
+}
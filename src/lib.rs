@@ -5,19 +5,30 @@ use std::fs::{self, File};
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 
+use age::secrecy::ExposeSecret;
+use fs2::FileExt;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
 use zip::write::FileOptions as ZipFileOptions;
 
+mod credential;
+mod kdf;
+mod otp;
 mod password;
 pub mod password_generation;
+mod shamir;
 
+pub use credential::KeyringCredential;
+pub use kdf::KdfParams;
+pub use otp::{OtpAlgorithm, OtpItem};
 pub use password::PasswordItem;
 pub use password::SecurityQuestion;
+pub use shamir::Share;
 
 struct ItemInMemory {
     mimetype: String,
     hidden: bool,
-    updated_data: Option<Vec<u8>>,
+    updated_data: Option<SecretBytes>,
 }
 
 struct ItemSerializer<'a>(&'a HashMap<String, ItemInMemory>);
@@ -51,10 +62,31 @@ impl Serialize for ItemSerializer<'_> {
 
 type EncryptedZipArchiveFile = zip::ZipArchive<age::stream::StreamReader<File>>;
 
+/// PBKDF2 iterations used to stretch a keyring's "reveal" passphrase, the first time a hidden
+/// item is set. Matches the default for the master passphrase in the `keyring` CLI.
+const REVEAL_KDF_ITERATIONS: u32 = 210_000;
+/// Salt size, in bytes, used when generating reveal-passphrase KDF parameters.
+const REVEAL_KDF_SALT_SIZE: usize = 32;
+
+/// Policy controls applied when creating a new keyring.
+pub struct KeyringOptions {
+    /// The minimum acceptable length, in characters, for the master passphrase.
+    pub min_passphrase_len: usize,
+}
+
+impl Default for KeyringOptions {
+    fn default() -> KeyringOptions {
+        KeyringOptions {
+            min_passphrase_len: 12,
+        }
+    }
+}
+
 /// An encrypted keyring containing credentials & other sensitive data.
 pub struct Keyring {
     path: PathBuf,
-    password: Secret,
+    /// How the keyring's master key is protected, and the material needed to exercise it.
+    credential: KeyringCredential,
     /// Items which are on the keyring. Metadata is always in memory for an open keyring, contents
     /// are if they are not yet written out.
     items: HashMap<String, ItemInMemory>,
@@ -62,30 +94,85 @@ pub struct Keyring {
     /// be purged from the underlying ZIP.
     deleted_items: HashSet<String>,
     zip_archive: Option<EncryptedZipArchiveFile>,
+    /// KDF parameters for stretching the "reveal" passphrase that protects `hidden` items.
+    /// `None` until the first hidden item is set.
+    reveal_kdf_params: Option<KdfParams>,
+    /// The reveal passphrase itself, supplied via [`Keyring::unlock_hidden_items`]. Not persisted
+    /// anywhere; without it, hidden items stay sealed for the rest of this keyring's lifetime.
+    reveal_secret: Option<Secret>,
+    /// An advisory, cross-process exclusive lock on the keyring file, held for as long as this
+    /// `Keyring` is. Re-acquired against the freshly-renamed file on every [`Keyring::save`],
+    /// since the atomic rename gives the file a new identity out from under the old lock. Released
+    /// automatically when this `Keyring` (and this file descriptor) is dropped.
+    lock: File,
 }
 
 impl Keyring {
-    /// Create a new keyring at the given path, with the given password.
-    pub fn create(path: PathBuf, password: Secret) -> Result<Keyring, KeyringError> {
-        let file = File::options()
+    /// Create a new keyring at the given path, protected by the given credential.
+    ///
+    /// For a [`KeyringCredential::Passphrase`], rejects passphrases shorter than
+    /// `options.min_passphrase_len` with [`KeyringErrorRepr::PassphraseTooShort`], rather than
+    /// silently accepting a weak master password.
+    pub fn create(
+        path: PathBuf,
+        credential: KeyringCredential,
+        options: KeyringOptions,
+    ) -> Result<Keyring, KeyringError> {
+        if let KeyringCredential::Passphrase { password, .. } = &credential {
+            if password.0.chars().count() < options.min_passphrase_len {
+                return Err(KeyringErrorRepr::PassphraseTooShort(options.min_passphrase_len).into());
+            }
+        }
+
+        let mut file = File::options()
             .create_new(true)
             .write(true)
             .open(&path)
             .map_err(KeyringErrorRepr::Io)?;
+        let lock = file.try_clone().map_err(KeyringErrorRepr::Io)?;
+        acquire_lock(&lock, LockMode::Blocking)?;
         let mut keyring = Keyring {
             path,
-            password,
+            credential,
             items: HashMap::new(),
             deleted_items: HashSet::new(),
             zip_archive: None,
+            reveal_kdf_params: None,
+            reveal_secret: None,
+            lock,
         };
+        write_header(&mut file, &header_for(&keyring))?;
         keyring.save_into(file)?;
         Ok(keyring)
     }
 
-    /// Load the keyring at the given path with the given password.
-    pub fn load(path: PathBuf, password: Secret) -> Result<Keyring, KeyringError> {
-        let mut zip_archive = Self::load_zip_archive(&path, &password)?;
+    /// Load the keyring at the given path with the given credential.
+    ///
+    /// `credential` must be of the same kind (passphrase, or recipients) that the keyring was
+    /// created with; a [`KeyringCredential::Recipients`] need only supply `identities`, since the
+    /// recipients themselves are persisted in the keyring's header.
+    ///
+    /// Blocks until any other process holding the keyring's advisory lock releases it. See
+    /// [`Keyring::try_load`] to fail fast instead.
+    pub fn load(path: PathBuf, credential: KeyringCredential) -> Result<Keyring, KeyringError> {
+        Self::load_with_lock_mode(path, credential, LockMode::Blocking)
+    }
+
+    /// Like [`Keyring::load`], but fails immediately with [`KeyringErrorRepr::Locked`] instead of
+    /// waiting if another process already holds the keyring's advisory lock.
+    pub fn try_load(path: PathBuf, credential: KeyringCredential) -> Result<Keyring, KeyringError> {
+        Self::load_with_lock_mode(path, credential, LockMode::NonBlocking)
+    }
+
+    fn load_with_lock_mode(
+        path: PathBuf,
+        credential: KeyringCredential,
+        lock_mode: LockMode,
+    ) -> Result<Keyring, KeyringError> {
+        let lock = File::open(&path).map_err(KeyringErrorRepr::Io)?;
+        acquire_lock(&lock, lock_mode)?;
+        let (credential, reveal_kdf_params, mut zip_archive) =
+            Self::load_zip_archive(&path, &credential)?;
         let magic_file = zip_archive
             .by_name("META-INF/MAGIC")
             .map_err(KeyringErrorRepr::ZipReadErr)?;
@@ -96,30 +183,75 @@ impl Keyring {
         let items = load_contents(contents)?;
         Ok(Keyring {
             path,
-            password,
+            credential,
             items,
             deleted_items: HashSet::new(),
             zip_archive: Some(zip_archive),
+            reveal_kdf_params,
+            reveal_secret: None,
+            lock,
         })
     }
 
+    /// Open the encrypted ZIP body at `path`, returning the credential actually used to open it
+    /// (with the persisted recipients/KDF parameters merged in), the KDF parameters for the
+    /// "reveal" passphrase (if any hidden items have ever been set), and the opened archive.
     fn load_zip_archive(
         path: &Path,
-        password: &Secret,
-    ) -> Result<EncryptedZipArchiveFile, KeyringError> {
-        let file = File::open(&path).map_err(KeyringErrorRepr::Io)?;
-        let decryptor =
-            match age::Decryptor::new(file).map_err(KeyringErrorRepr::DecryptionError)? {
-                age::Decryptor::Recipients(_) => {
-                    return Err(KeyringErrorRepr::UnexpectedNonPasswordAgeData.into())
-                }
-                age::Decryptor::Passphrase(pd) => pd,
-            };
-        // FIXME: how to let the user control the work factor value, here?
-        let decryptor = decryptor
-            .decrypt(&password.0.clone().into(), Some(20))
-            .map_err(KeyringErrorRepr::DecryptionError)?;
-        Ok(zip::ZipArchive::new(decryptor).map_err(KeyringErrorRepr::ZipReadErr)?)
+        credential: &KeyringCredential,
+    ) -> Result<(KeyringCredential, Option<KdfParams>, EncryptedZipArchiveFile), KeyringError> {
+        let mut file = File::open(path).map_err(KeyringErrorRepr::Io)?;
+        let header = read_header(&mut file)?;
+        let credential = match (header.credential, credential) {
+            (
+                StoredCredentialHeader::Passphrase(kdf_params),
+                KeyringCredential::Passphrase { password, .. },
+            ) => KeyringCredential::Passphrase {
+                password: password.clone(),
+                kdf_params,
+            },
+            (
+                StoredCredentialHeader::Recipients(recipients),
+                KeyringCredential::Recipients { identities, .. },
+            ) => KeyringCredential::Recipients {
+                recipients,
+                identities: identities.clone(),
+            },
+            _ => return Err(KeyringErrorRepr::CredentialKindMismatch.into()),
+        };
+
+        let decryptor = age::Decryptor::new(file).map_err(KeyringErrorRepr::DecryptionError)?;
+        let reader = match (&credential, decryptor) {
+            (
+                KeyringCredential::Passphrase { password, kdf_params },
+                age::Decryptor::Passphrase(pd),
+            ) => {
+                let stretched_password = kdf_params.stretch(password);
+                // `age::Encryptor::with_user_passphrase` doesn't take a work-factor argument; age
+                // always benchmarks its own work factor at encrypt time, so `kdf_params.age_work_factor`
+                // never actually configured encryption strength and can't be trusted as the value
+                // the message was really written with. Passing it as `max_work_factor` would reject
+                // otherwise-good decryptions the moment the encrypting machine benchmarked higher
+                // (a faster CPU, or just noise) than whatever was recorded at `create` time.
+                pd.decrypt(&stretched_password.0.clone().into(), None)
+                    .map_err(KeyringErrorRepr::DecryptionError)?
+            }
+            (KeyringCredential::Recipients { identities, .. }, age::Decryptor::Recipients(rd)) => {
+                let parsed_identities = identities
+                    .iter()
+                    .map(|s| credential::parse_identity(s.as_str()))
+                    .collect::<anyhow::Result<Vec<_>>>()
+                    .map_err(KeyringErrorRepr::IdentityParseError)?;
+                rd.decrypt(parsed_identities.iter().map(|i| i.as_ref()))
+                    .map_err(KeyringErrorRepr::DecryptionError)?
+            }
+            _ => return Err(KeyringErrorRepr::CredentialKindMismatch.into()),
+        };
+        Ok((
+            credential,
+            header.reveal_kdf_params,
+            zip::ZipArchive::new(reader).map_err(KeyringErrorRepr::ZipReadErr)?,
+        ))
     }
 
     pub fn save(&mut self) -> Result<(), KeyringError> {
@@ -130,15 +262,22 @@ impl Keyring {
             parent.join(file_name)
         };
 
-        let file = File::options()
+        let mut file = File::options()
             .create_new(true)
             .write(true)
             .open(&temp_path)
             .map_err(KeyringErrorRepr::Io)?;
+        // The rename below gives the keyring file a new underlying identity, so our existing
+        // lock (on the old identity) won't carry over to it; acquire a fresh one against this
+        // file before it takes the keyring's name.
+        let new_lock = file.try_clone().map_err(KeyringErrorRepr::Io)?;
+        acquire_lock(&new_lock, LockMode::Blocking)?;
+        write_header(&mut file, &header_for(self))?;
 
         self.save_into(file)?;
         fs::rename(temp_path, &self.path).map_err(KeyringErrorRepr::Io)?;
-        self.zip_archive = Some(Self::load_zip_archive(&self.path, &self.password)?);
+        self.lock = new_lock;
+        self.zip_archive = Some(Self::load_zip_archive(&self.path, &self.credential)?.2);
         for item in self.items.values_mut() {
             item.updated_data = None;
         }
@@ -146,7 +285,7 @@ impl Keyring {
     }
 
     fn save_into(&mut self, file: File) -> Result<(), KeyringError> {
-        let encryptor = age::Encryptor::with_user_passphrase(self.password.0.clone().into());
+        let encryptor = build_encryptor(&self.credential)?;
 
         // ZIP writing requires Seek (to update the file headers as the archive is written) but the
         // age writer isn't `Seek`.
@@ -252,7 +391,8 @@ impl Keyring {
             hidden: false,
             updated_data: Some(
                 item.serialize()
-                    .map_err(KeyringErrorRepr::ItemSerializationError)?,
+                    .map_err(KeyringErrorRepr::ItemSerializationError)?
+                    .into(),
             ),
         };
         self.deleted_items.remove(&name);
@@ -260,6 +400,93 @@ impl Keyring {
         Ok(())
     }
 
+    /// Set an item on the keyring as `hidden`: its serialized bytes are encrypted with the
+    /// keyring's "reveal" passphrase before they're written, so they stay sealed even in an
+    /// unlocked keyring until someone calls [`Keyring::unlock_hidden_items`] with that passphrase.
+    ///
+    /// Requires [`Keyring::unlock_hidden_items`] to have already been called, since that
+    /// passphrase is what the item is encrypted with; fails with
+    /// [`KeyringErrorRepr::ItemLocked`] otherwise.
+    pub fn set_item_hidden<I: KeyringItem>(
+        &mut self,
+        name: String,
+        item: I,
+    ) -> Result<(), KeyringError> {
+        let serialized = item
+            .serialize()
+            .map_err(KeyringErrorRepr::ItemSerializationError)?;
+        let encrypted = self.encrypt_hidden(&serialized)?;
+        let new_item = ItemInMemory {
+            mimetype: I::mimetype().to_owned(),
+            hidden: true,
+            updated_data: Some(encrypted.into()),
+        };
+        self.deleted_items.remove(&name);
+        self.items.insert(name, new_item);
+        Ok(())
+    }
+
+    /// Supply the "reveal" passphrase needed to encrypt new hidden items, and to decrypt existing
+    /// ones, for the rest of this keyring's lifetime. Wrong passphrases aren't rejected here;
+    /// they simply fail to decrypt when a hidden item is actually read.
+    pub fn unlock_hidden_items(&mut self, secret: Secret) {
+        self.reveal_secret = Some(secret);
+    }
+
+    /// Encrypt `data` with the keyring's reveal passphrase, generating fresh KDF parameters for
+    /// it the first time a hidden item is set.
+    fn encrypt_hidden(&mut self, data: &[u8]) -> Result<Vec<u8>, KeyringError> {
+        let reveal_secret = self
+            .reveal_secret
+            .clone()
+            .ok_or(KeyringErrorRepr::ItemLocked)?;
+        let reveal_kdf_params = self.reveal_kdf_params.get_or_insert_with(|| {
+            KdfParams::generate(
+                REVEAL_KDF_ITERATIONS,
+                REVEAL_KDF_SALT_SIZE,
+                KdfParams::DEFAULT_AGE_WORK_FACTOR,
+            )
+        });
+        let stretched = reveal_kdf_params.stretch(&reveal_secret);
+        let encryptor = age::Encryptor::with_user_passphrase(stretched.0.clone().into());
+        let mut encrypted = Vec::new();
+        let mut writer = encryptor
+            .wrap_output(&mut encrypted)
+            .map_err(KeyringErrorRepr::EncryptionError)?;
+        writer.write_all(data).map_err(KeyringErrorRepr::Io)?;
+        writer.finish().map_err(KeyringErrorRepr::Io)?;
+        Ok(encrypted)
+    }
+
+    /// Decrypt `data` with the keyring's reveal passphrase.
+    fn decrypt_hidden(&self, data: &[u8]) -> Result<Vec<u8>, KeyringError> {
+        let reveal_secret = self
+            .reveal_secret
+            .as_ref()
+            .ok_or(KeyringErrorRepr::ItemLocked)?;
+        let reveal_kdf_params = self
+            .reveal_kdf_params
+            .as_ref()
+            .expect("a hidden item exists, so reveal KDF params must have been generated for it");
+        let stretched = reveal_kdf_params.stretch(reveal_secret);
+        let decryptor = match age::Decryptor::new(io::Cursor::new(data))
+            .map_err(KeyringErrorRepr::DecryptionError)?
+        {
+            age::Decryptor::Passphrase(pd) => pd,
+            age::Decryptor::Recipients(_) => return Err(KeyringErrorRepr::CredentialKindMismatch.into()),
+        };
+        // See the comment in `load_zip_archive` on why `age_work_factor` isn't passed here as
+        // `max_work_factor`: it was never the work factor age actually encrypted with.
+        let mut reader = decryptor
+            .decrypt(&stretched.0.clone().into(), None)
+            .map_err(KeyringErrorRepr::DecryptionError)?;
+        let mut decrypted = Vec::new();
+        reader
+            .read_to_end(&mut decrypted)
+            .map_err(KeyringErrorRepr::Io)?;
+        Ok(decrypted)
+    }
+
     /// Delete an item from the keyring.
     ///
     /// Returns `true` if the item existed, and was deleted, or `false` if that item didn't exist,
@@ -291,27 +518,49 @@ impl Keyring {
         }
     }
 
+    /// Get an item's raw, decoded bytes.
+    ///
+    /// Fails with [`KeyringErrorRepr::ItemLocked`] if the item is `hidden` and
+    /// [`Keyring::unlock_hidden_items`] hasn't been called; if it was called with the wrong
+    /// passphrase, reading the item fails with a decryption error instead.
     pub fn get_item_raw(&mut self, name: &str) -> Result<Option<Item>, KeyringError> {
         let item_in_mem = match self.items.get(name) {
             Some(i) => i,
             None => return Ok(None),
         };
+        if item_in_mem.hidden && self.reveal_secret.is_none() {
+            return Err(KeyringErrorRepr::ItemLocked.into());
+        }
         if let Some(data) = item_in_mem.updated_data.as_deref() {
+            let data = if item_in_mem.hidden {
+                Cow::Owned(self.decrypt_hidden(data)?)
+            } else {
+                Cow::from(data)
+            };
             Ok(Some(Item {
                 mimetype: &item_in_mem.mimetype,
-                data: Cow::from(data),
+                data,
             }))
         } else {
             let zip_item_name = format!("items/{}", name);
-            let mut item = self
-                .zip_archive
-                .as_mut()
-                .unwrap()
-                .by_name(&zip_item_name)
-                .map_err(KeyringErrorRepr::ZipReadErr)?;
             let mut item_data = Vec::new();
-            item.read_to_end(&mut item_data)
-                .map_err(KeyringErrorRepr::Io)?;
+            {
+                let mut item = self
+                    .zip_archive
+                    .as_mut()
+                    .unwrap()
+                    .by_name(&zip_item_name)
+                    .map_err(KeyringErrorRepr::ZipReadErr)?;
+                item.read_to_end(&mut item_data)
+                    .map_err(KeyringErrorRepr::Io)?;
+                // `ZipFile`'s `Drop` impl keeps its mutable borrow of `self.zip_archive` alive to
+                // the end of scope; end that scope here, before `decrypt_hidden` needs `&self`.
+            }
+            let item_data = if item_in_mem.hidden {
+                self.decrypt_hidden(&item_data)?
+            } else {
+                item_data
+            };
             Ok(Some(Item {
                 mimetype: &item_in_mem.mimetype,
                 data: Cow::from(item_data),
@@ -329,6 +578,73 @@ impl Keyring {
         self.items.insert(name, new_item);
         Ok(())
     }
+
+    /// Like [`Keyring::set_item_raw`], but marks the item `hidden`, encrypting its bytes with the
+    /// keyring's reveal passphrase. See [`Keyring::set_item_hidden`].
+    pub fn set_item_raw_hidden(&mut self, name: String, item: ItemOwned) -> Result<(), KeyringError> {
+        let encrypted = self.encrypt_hidden(&item.data)?;
+        let new_item = ItemInMemory {
+            mimetype: item.mimetype,
+            hidden: true,
+            updated_data: Some(encrypted.into()),
+        };
+        self.deleted_items.remove(&name);
+        self.items.insert(name, new_item);
+        Ok(())
+    }
+
+    /// Check which kind of credential the keyring at `path` is protected with, without decrypting
+    /// anything. Lets a caller decide whether to prompt for a passphrase or an identity before
+    /// calling [`Keyring::load`].
+    pub fn peek_credential_kind(path: &Path) -> Result<CredentialKind, KeyringError> {
+        let mut file = File::open(path).map_err(KeyringErrorRepr::Io)?;
+        Ok(match read_header(&mut file)?.credential {
+            StoredCredentialHeader::Passphrase(_) => CredentialKind::Passphrase,
+            StoredCredentialHeader::Recipients(_) => CredentialKind::Recipients,
+        })
+    }
+
+    /// Protect this keyring with a `k`-of-`n` Shamir secret-sharing scheme instead of whatever
+    /// credential it currently has: generates a fresh X25519 recipient, re-encrypts the keyring
+    /// to it, then splits its identity into `n` [`Share`]s, any `k` of which can reconstruct it
+    /// via [`Keyring::load_from_shares`]. Distribute the returned shares to `n` custodians; the
+    /// keyring can then only be recovered when a threshold of `k` of them cooperate.
+    pub fn split_passphrase(&mut self, k: u8, n: u8) -> Result<Vec<Share>, KeyringError> {
+        let identity = age::x25519::Identity::generate();
+        let recipient = identity.to_public();
+        // `Identity::to_string` returns a `secrecy::SecretString`, not a plain `String`; unwrap it
+        // via `expose_secret` to get at the actual identity text.
+        let identity_string = identity.to_string();
+        let identity_str = identity_string.expose_secret();
+        self.credential = KeyringCredential::Recipients {
+            recipients: vec![recipient.to_string()],
+            identities: vec![Secret::from(identity_str.clone())],
+        };
+        self.save()?;
+        Ok(shamir::split(identity_str.as_bytes(), k, n))
+    }
+
+    /// Load the keyring at `path` by reconstructing the recovery identity from `k` or more
+    /// [`Share`]s produced by a prior [`Keyring::split_passphrase`] call.
+    pub fn load_from_shares(path: PathBuf, shares: &[Share]) -> Result<Keyring, KeyringError> {
+        let identity_bytes = shamir::combine(shares).map_err(KeyringErrorRepr::InvalidShares)?;
+        let identity = String::from_utf8(identity_bytes)
+            .map_err(|_| KeyringErrorRepr::InvalidShares(shamir::CombineError::InconsistentShares))?;
+        Self::load(
+            path,
+            KeyringCredential::Recipients {
+                recipients: Vec::new(),
+                identities: vec![Secret::from(identity)],
+            },
+        )
+    }
+}
+
+/// Which kind of credential a keyring on disk is protected with. See [`Keyring::peek_credential_kind`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CredentialKind {
+    Passphrase,
+    Recipients,
 }
 
 pub struct Item<'a> {
@@ -336,9 +652,46 @@ pub struct Item<'a> {
     pub data: Cow<'a, [u8]>,
 }
 
+impl Drop for Item<'_> {
+    fn drop(&mut self) {
+        // Only owned data is ours to wipe; a borrowed `Cow` points into the keyring's still-live
+        // in-memory item buffer, which isn't ours to zero out from under it.
+        if let Cow::Owned(data) = &mut self.data {
+            data.zeroize();
+        }
+    }
+}
+
 pub struct ItemOwned {
     pub mimetype: String,
-    pub data: Vec<u8>,
+    pub data: SecretBytes,
+}
+
+/// Owned bytes that are zeroized when dropped. Used for item buffers that pass through
+/// plaintext, so a stray clone or an early return doesn't leave it sitting in a freed heap page.
+///
+/// Wraps `Vec<u8>` rather than `ItemOwned` itself implementing `Drop`, so callers can still move
+/// `mimetype` and `data` out of an `ItemOwned` independently.
+pub struct SecretBytes(Vec<u8>);
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl std::ops::Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(data: Vec<u8>) -> SecretBytes {
+        SecretBytes(data)
+    }
 }
 
 pub struct ItemMetadata<'a> {
@@ -392,10 +745,25 @@ enum KeyringErrorRepr {
     #[error("decryption error: {0}")]
     DecryptionError(age::DecryptError),
     #[error(
-        "the encrypted data (an \"age\" file) was encrypted to particular asymmetric keys, whereas \
-         it is expected to be encrypted with a passphrase"
+        "this keyring was created with a different kind of credential (passphrase vs. \
+         recipients) than the one supplied to open it"
     )]
-    UnexpectedNonPasswordAgeData,
+    CredentialKindMismatch,
+    #[error("failed to parse an age recipient: {0}")]
+    RecipientParseError(#[source] anyhow::Error),
+    #[error("failed to parse an age identity: {0}")]
+    IdentityParseError(#[source] anyhow::Error),
+    #[error("a recipients-based keyring requires at least one recipient")]
+    NoRecipients,
+    #[error("invalid set of recovery shares: {0}")]
+    InvalidShares(#[source] shamir::CombineError),
+    #[error(
+        "this item is hidden, and the reveal passphrase hasn't been supplied via \
+         Keyring::unlock_hidden_items"
+    )]
+    ItemLocked,
+    #[error("another process already has this keyring locked; try again later")]
+    Locked,
     #[error("error while writing ZIP archive: {0}")]
     ZipWriteErr(#[source] zip::result::ZipError),
     #[error("error while reading ZIP archive: {0}")]
@@ -406,8 +774,17 @@ enum KeyringErrorRepr {
     ItemSerializationError(#[source] anyhow::Error),
     #[error("failed to deserialize item: {0}")]
     ItemDeserializationError(#[source] anyhow::Error),
+    #[error("master passphrase must be at least {0} characters long")]
+    PassphraseTooShort(usize),
 }
 
+/// A secret value (a passphrase, or a key derived from one) that hides itself from `Debug` and
+/// is zeroized in place when dropped, so it doesn't linger in freed heap pages.
+///
+/// The plaintext is exposed only by [`Secret::as_str`] and by its `Serialize` impl (which writes
+/// the plaintext as a bare string); prefer borrowing it for as short a
+/// window as possible over cloning it, since a clone is a second buffer this type can't zero on
+/// your behalf until it, too, is dropped.
 #[derive(Clone, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(transparent)]
 pub struct Secret(String);
@@ -426,6 +803,115 @@ impl From<String> for Secret {
     }
 }
 
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// What's persisted, in the clear, in a keyring's header: just enough to know how to derive the
+/// key used to decrypt the body, without needing to decrypt anything first.
+#[derive(Deserialize, Serialize)]
+struct StoredHeader {
+    credential: StoredCredentialHeader,
+    /// KDF parameters for stretching the "reveal" passphrase that protects `hidden` items.
+    /// `None` until the first hidden item is set.
+    #[serde(default)]
+    reveal_kdf_params: Option<KdfParams>,
+}
+
+#[derive(Deserialize, Serialize)]
+enum StoredCredentialHeader {
+    Passphrase(KdfParams),
+    /// The recipients a keyring is encrypted to. These are public, so persisting them in the
+    /// clear alongside the passphrase KDF params leaks nothing; it lets `load` and `save` always
+    /// re-encrypt to the same set of recipients without the caller having to repeat them.
+    Recipients(Vec<String>),
+}
+
+fn header_for(keyring: &Keyring) -> StoredHeader {
+    let credential = match &keyring.credential {
+        KeyringCredential::Passphrase { kdf_params, .. } => {
+            StoredCredentialHeader::Passphrase(kdf_params.clone())
+        }
+        KeyringCredential::Recipients { recipients, .. } => {
+            StoredCredentialHeader::Recipients(recipients.clone())
+        }
+    };
+    StoredHeader {
+        credential,
+        reveal_kdf_params: keyring.reveal_kdf_params.clone(),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum LockMode {
+    /// Wait for the lock to become available.
+    Blocking,
+    /// Fail immediately with [`KeyringErrorRepr::Locked`] if the lock isn't available.
+    NonBlocking,
+}
+
+/// Take an advisory, cross-process exclusive lock on `file`, used to prevent two `Keyring`
+/// handles (in this process or another) from concurrently mutating and saving the same file.
+fn acquire_lock(file: &File, mode: LockMode) -> Result<(), KeyringErrorRepr> {
+    match mode {
+        LockMode::Blocking => file.lock_exclusive().map_err(KeyringErrorRepr::Io),
+        LockMode::NonBlocking => file.try_lock_exclusive().map_err(|err| {
+            if err.kind() == fs2::lock_contended_error().kind() {
+                KeyringErrorRepr::Locked
+            } else {
+                KeyringErrorRepr::Io(err)
+            }
+        }),
+    }
+}
+
+fn build_encryptor(credential: &KeyringCredential) -> Result<age::Encryptor, KeyringErrorRepr> {
+    match credential {
+        KeyringCredential::Passphrase {
+            password,
+            kdf_params,
+        } => {
+            let stretched_password = kdf_params.stretch(password);
+            Ok(age::Encryptor::with_user_passphrase(
+                stretched_password.0.clone().into(),
+            ))
+        }
+        KeyringCredential::Recipients { recipients, .. } => {
+            let parsed_recipients = recipients
+                .iter()
+                .map(|s| credential::parse_recipient(s))
+                .collect::<anyhow::Result<Vec<_>>>()
+                .map_err(KeyringErrorRepr::RecipientParseError)?;
+            age::Encryptor::with_recipients(parsed_recipients)
+                .ok_or(KeyringErrorRepr::NoRecipients)
+        }
+    }
+}
+
+/// Write the header that precedes the age-encrypted body of a keyring file: a big-endian `u32`
+/// length, followed by that many bytes of JSON-encoded [`StoredHeader`].
+///
+/// This has to live outside the encrypted body, since it's needed to derive the key (or select
+/// the recipients) used to decrypt that body in the first place.
+fn write_header(file: &mut File, header: &StoredHeader) -> Result<(), KeyringErrorRepr> {
+    let encoded = serde_json::to_vec(header).map_err(KeyringErrorRepr::SerializationFailure)?;
+    file.write_all(&(encoded.len() as u32).to_be_bytes())
+        .map_err(KeyringErrorRepr::Io)?;
+    file.write_all(&encoded).map_err(KeyringErrorRepr::Io)?;
+    Ok(())
+}
+
+fn read_header(file: &mut File) -> Result<StoredHeader, KeyringErrorRepr> {
+    let mut len_buf = [0u8; 4];
+    file.read_exact(&mut len_buf).map_err(KeyringErrorRepr::Io)?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+    let mut encoded = vec![0u8; len];
+    file.read_exact(&mut encoded).map_err(KeyringErrorRepr::Io)?;
+    serde_json::from_slice(&encoded).map_err(KeyringErrorRepr::OuterLayerDecodeFailed)
+}
+
 static MAGIC: &str = "application/prs.thanatos.keyring";
 
 fn verify_magic(mut rdr: impl Read) -> Result<(), KeyringErrorRepr> {
@@ -0,0 +1,154 @@
+//! One-time-password credential items: RFC 4226 HOTP, with RFC 6238 TOTP built on top of it.
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+/// Which HMAC hash function backs an [`OtpItem`]'s code generation.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// A TOTP/HOTP one-time-password seed, and the parameters needed to compute codes from it.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OtpItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    /// The shared secret, kept base64-encoded inside a [`crate::Secret`]. It usually starts out
+    /// base32-encoded, per the usual `otpauth://` provisioning URI convention; decode it once via
+    /// [`OtpItem::from_base32_secret`] rather than storing the base32 form directly.
+    pub secret: crate::Secret,
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    /// The validity period of each TOTP code, in seconds. Unused by [`OtpItem::hotp_at`].
+    pub period: u64,
+}
+
+impl OtpItem {
+    /// The digit count used by most authenticator apps when none is given explicitly.
+    pub const DEFAULT_DIGITS: u32 = 6;
+    /// The TOTP period, in seconds, used by most authenticator apps when none is given
+    /// explicitly.
+    pub const DEFAULT_PERIOD: u64 = 30;
+    /// The range of `digits` values [`format_code`] can render without overflowing `10u32.pow`.
+    pub const VALID_DIGITS: std::ops::RangeInclusive<u32> = 1..=9;
+
+    /// Build an `OtpItem` from a base32-encoded shared secret, as found in most `otpauth://`
+    /// provisioning URIs.
+    pub fn from_base32_secret(
+        base32_secret: &str,
+        algorithm: OtpAlgorithm,
+        digits: u32,
+        period: u64,
+    ) -> Result<OtpItem, anyhow::Error> {
+        if !Self::VALID_DIGITS.contains(&digits) {
+            anyhow::bail!(
+                "digits must be between {} and {}, but {} was given",
+                Self::VALID_DIGITS.start(),
+                Self::VALID_DIGITS.end(),
+                digits
+            );
+        }
+        let raw_secret = base32::decode(base32::Alphabet::RFC4648 { padding: false }, base32_secret)
+            .ok_or_else(|| anyhow::anyhow!("{:?} is not a valid base32 OTP secret", base32_secret))?;
+        Ok(OtpItem {
+            username: None,
+            issuer: None,
+            secret: crate::Secret::from(
+                base64::engine::general_purpose::STANDARD.encode(raw_secret),
+            ),
+            algorithm,
+            digits,
+            period,
+        })
+    }
+
+    /// Compute the RFC 4226 HOTP code for counter value `counter`.
+    pub fn hotp_at(&self, counter: u64) -> Result<String, anyhow::Error> {
+        let key = base64::engine::general_purpose::STANDARD.decode(self.secret.as_str())?;
+        let counter_be = counter.to_be_bytes();
+        let hash: Vec<u8> = match self.algorithm {
+            OtpAlgorithm::Sha1 => {
+                let mut mac = Hmac::<Sha1>::new_from_slice(&key)
+                    .expect("HMAC accepts a key of any length");
+                mac.update(&counter_be);
+                mac.finalize().into_bytes().to_vec()
+            }
+            OtpAlgorithm::Sha256 => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(&key)
+                    .expect("HMAC accepts a key of any length");
+                mac.update(&counter_be);
+                mac.finalize().into_bytes().to_vec()
+            }
+            OtpAlgorithm::Sha512 => {
+                let mut mac = Hmac::<Sha512>::new_from_slice(&key)
+                    .expect("HMAC accepts a key of any length");
+                mac.update(&counter_be);
+                mac.finalize().into_bytes().to_vec()
+            }
+        };
+        Ok(format_code(dynamic_truncate(&hash), self.digits))
+    }
+
+    /// Compute the RFC 6238 TOTP code valid at `unix_secs`.
+    pub fn code_at(&self, unix_secs: u64) -> Result<String, anyhow::Error> {
+        self.hotp_at(unix_secs / self.period)
+    }
+
+    /// Compute the RFC 6238 TOTP code valid right now.
+    pub fn current_code(&self) -> Result<String, anyhow::Error> {
+        let unix_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("system clock is set to before the Unix epoch")
+            .as_secs();
+        self.code_at(unix_secs)
+    }
+}
+
+impl crate::KeyringItem for OtpItem {
+    fn mimetype() -> &'static str {
+        "application/prs.thanatos.keyring.otp+json"
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>, anyhow::Error> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    fn deserialize(data: &[u8]) -> Result<Self, anyhow::Error> {
+        let item: OtpItem = serde_json::from_slice(data)?;
+        if !Self::VALID_DIGITS.contains(&item.digits) {
+            anyhow::bail!(
+                "digits must be between {} and {}, but {} was stored",
+                Self::VALID_DIGITS.start(),
+                Self::VALID_DIGITS.end(),
+                item.digits
+            );
+        }
+        Ok(item)
+    }
+}
+
+/// Dynamic truncation, per RFC 4226 section 5.3: take the low nibble of the last byte as an
+/// offset, read the 4 bytes at that offset big-endian, and mask off the sign bit.
+fn dynamic_truncate(hash: &[u8]) -> u32 {
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let truncated = [
+        hash[offset],
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ];
+    u32::from_be_bytes(truncated) & 0x7fff_ffff
+}
+
+fn format_code(value: u32, digits: u32) -> String {
+    let modulus = 10u32.pow(digits);
+    format!("{:0width$}", value % modulus, width = digits as usize)
+}
@@ -3,6 +3,14 @@
 use rand::seq::SliceRandom;
 use rand::{CryptoRng, Rng};
 
+static WORDLIST_TEXT: &str = include_str!("wordlist.txt");
+
+/// An EFF-style wordlist, suitable for diceware-like passphrase generation: 7776 words, so that
+/// each word can be indexed by five dice rolls (6^5 == 7776).
+pub fn wordlist() -> Vec<&'static str> {
+    WORDLIST_TEXT.lines().collect()
+}
+
 /// Generate a simple, impossible-to-guess password by just randomly sampling the given alphabet.
 ///
 /// These are ugly, hard to remember passwords, but perfect if you're just copying them from a
@@ -22,3 +30,34 @@ where
     }
     secret
 }
+
+/// Generate a diceware-style memorable passphrase by uniformly sampling `words` entries from
+/// `wordlist` and joining them with `separator`.
+///
+/// Like [`generate_random_password`], this relies on `rand`'s underlying uniform sampler
+/// (`SliceRandom::choose`) to avoid modulo bias via rejection sampling.
+pub fn generate_passphrase<R>(
+    rng: &mut R,
+    wordlist: &[&str],
+    words: usize,
+    separator: char,
+) -> crate::Secret
+where
+    R: Rng + CryptoRng,
+{
+    let mut secret = crate::Secret(String::new());
+    for i in 0..words {
+        if i > 0 {
+            secret.0.push(separator);
+        }
+        let word = wordlist.choose(rng).unwrap();
+        secret.0.push_str(word);
+    }
+    secret
+}
+
+/// The entropy, in bits, of a passphrase generated by [`generate_passphrase`] with the given
+/// wordlist size and word count.
+pub fn passphrase_entropy_bits(wordlist_len: usize, words: usize) -> f64 {
+    words as f64 * (wordlist_len as f64).log2()
+}
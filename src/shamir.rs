@@ -0,0 +1,148 @@
+//! Shamir Secret Sharing over GF(256), used to split a keyring's recovery identity across
+//! multiple custodians. See [`crate::Keyring::split_passphrase`].
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+/// One custodian's share of a secret split via [`split`]. `y` has one byte per byte of the
+/// original secret; `x` is the evaluation point shared by every byte's polynomial.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Share {
+    pub x: u8,
+    pub y: Vec<u8>,
+}
+
+/// Split `secret` into `n` shares, any `k` of which can reconstruct it via [`combine`].
+///
+/// For each byte of `secret`, builds a degree-`(k - 1)` polynomial over GF(256) whose constant
+/// term is that byte and whose other coefficients are random, then evaluates it at `x = 1..=n`.
+///
+/// # Panics
+///
+/// Panics if `k` is zero, or if `n < k`.
+pub(crate) fn split(secret: &[u8], k: u8, n: u8) -> Vec<Share> {
+    assert!(k > 0, "a threshold of 0 shares can never reconstruct a secret");
+    assert!(n >= k, "cannot require more shares ({k}) than are generated ({n})");
+
+    let mut rng = rand::thread_rng();
+    let mut shares: Vec<Share> = (1..=n)
+        .map(|x| Share {
+            x,
+            y: Vec::with_capacity(secret.len()),
+        })
+        .collect();
+
+    for &secret_byte in secret {
+        let mut coefficients = vec![secret_byte];
+        for _ in 1..k {
+            let mut buf = [0u8; 1];
+            rng.fill_bytes(&mut buf);
+            coefficients.push(buf[0]);
+        }
+        for share in shares.iter_mut() {
+            share.y.push(eval_polynomial(&coefficients, share.x));
+        }
+    }
+
+    shares
+}
+
+/// Reconstruct the original secret from `shares`, via Lagrange interpolation at `x = 0` over
+/// GF(256). Any `k` or more of the shares produced by the matching [`split`] call will
+/// reconstruct the same secret; fewer, or shares from different splits, will not.
+pub(crate) fn combine(shares: &[Share]) -> Result<Vec<u8>, CombineError> {
+    if shares.is_empty() {
+        return Err(CombineError::NoShares);
+    }
+    let secret_len = shares[0].y.len();
+    if shares.iter().any(|s| s.y.len() != secret_len) {
+        return Err(CombineError::InconsistentShares);
+    }
+    let mut xs: Vec<u8> = shares.iter().map(|s| s.x).collect();
+    xs.sort_unstable();
+    if xs.windows(2).any(|w| w[0] == w[1]) {
+        return Err(CombineError::InconsistentShares);
+    }
+
+    Ok((0..secret_len)
+        .map(|byte_index| lagrange_interpolate_at_zero(shares, byte_index))
+        .collect())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum CombineError {
+    #[error("no recovery shares were given")]
+    NoShares,
+    #[error("too few recovery shares, or shares from inconsistent splits, were given")]
+    InconsistentShares,
+}
+
+/// Evaluate a polynomial (lowest-degree coefficient first) at `x`, over GF(256), via Horner's
+/// method.
+fn eval_polynomial(coefficients: &[u8], x: u8) -> u8 {
+    let mut result = 0u8;
+    for &coefficient in coefficients.iter().rev() {
+        result = gf_add(gf_mul(result, x), coefficient);
+    }
+    result
+}
+
+fn lagrange_interpolate_at_zero(shares: &[Share], byte_index: usize) -> u8 {
+    let mut result = 0u8;
+    for (i, share_i) in shares.iter().enumerate() {
+        let mut term = share_i.y[byte_index];
+        for (j, share_j) in shares.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            // The Lagrange basis polynomial for `share_i`, evaluated at x = 0: in GF(2^n),
+            // subtraction is the same as addition (XOR), so `xj / (xi - xj)` becomes
+            // `xj / (xi ^ xj)`.
+            term = gf_mul(term, gf_div(share_j.x, gf_add(share_i.x, share_j.x)));
+        }
+        result = gf_add(result, term);
+    }
+    result
+}
+
+fn gf_add(a: u8, b: u8) -> u8 {
+    a ^ b
+}
+
+/// Multiply two GF(256) elements, reducing modulo the AES field polynomial `x^8 + x^4 + x^3 + x +
+/// 1` (0x11b).
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut result = 0u8;
+    for _ in 0..8 {
+        if b & 1 != 0 {
+            result ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    result
+}
+
+/// The multiplicative inverse of `a` in GF(256): since GF(256)* has order 255, `a^254 == a^-1`.
+fn gf_inv(a: u8) -> u8 {
+    assert!(a != 0, "0 has no multiplicative inverse in GF(256)");
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent > 0 {
+        if exponent & 1 != 0 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    gf_mul(a, gf_inv(b))
+}